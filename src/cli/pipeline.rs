@@ -2,8 +2,11 @@ use std::{collections::BTreeMap, fs::read, path::Path};
 
 use clap::ArgMatches;
 use mozjpeg::qtable;
-use rimage::codecs::mozjpeg::{MozJpegEncoder, MozJpegOptions};
-use zune_core::{bytestream::ZByteReader, options::EncoderOptions};
+use rimage::codecs::{
+    jpegli::DecodeLimits,
+    mozjpeg::{MozJpegEncoder, MozJpegOptions},
+};
+use zune_core::{bytestream::ZByteReader, options::DecoderOptions, options::EncoderOptions};
 use zune_image::{
     codecs::{
         farbfeld::FarbFeldEncoder, jpeg::JpegEncoder, jpeg_xl::JxlEncoder, png::PngEncoder,
@@ -14,8 +17,163 @@ use zune_image::{
     traits::{DecoderTrait, EncoderTrait, OperationsTrait},
 };
 
-pub fn decode<P: AsRef<Path>>(f: P) -> Result<Image, ImageErrors> {
-    Image::open(f.as_ref()).or_else(|e| {
+/// JPEG APP2 marker tag used to identify ICC profile chunks, per the ICC spec; kept in sync
+/// with the constant of the same name in `codecs::mozjpeg::encoder`.
+const ICC_MARKER_TAG: &[u8; 12] = b"ICC_PROFILE\0";
+
+/// JPEG APP4 marker tag `MozJpegEncoder` writes a deflate-compressed alpha sidecar under;
+/// kept in sync with the constant of the same name in `codecs::mozjpeg::encoder`.
+const ALPHA_MARKER_TAG: &[u8; 14] = b"RIMAGE_ALPHA\0\0";
+
+/// Whether `bytes` start with the JPEG SOI marker (0xFFD8), used to sniff file type instead of
+/// trusting the extension.
+fn has_jpeg_magic(bytes: &[u8]) -> bool {
+    bytes.starts_with(&[0xFF, 0xD8])
+}
+
+/// Scans raw JPEG bytes for every occurrence of `tag`, reassembling its chunks (a 1-based
+/// index byte then a total-count byte, as written by `write_chunked_app_marker` on the encode
+/// side) into one contiguous buffer, in index order.
+fn extract_chunked_app_marker(raw: &[u8], tag: &[u8]) -> Option<Vec<u8>> {
+    let mut chunks: BTreeMap<u8, &[u8]> = BTreeMap::new();
+    let mut pos = 0;
+
+    while let Some(offset) = raw[pos..].windows(tag.len()).position(|w| w == tag) {
+        let tag_start = pos + offset;
+        // The marker's own 2-byte big-endian length (counting itself, but not the FF/marker-id
+        // bytes before it) sits immediately before the tag, per the JPEG marker segment format.
+        // The tag can't legitimately appear in the first 2 bytes of the file, but don't trust
+        // that on untrusted input: a match there would underflow `tag_start - 2`.
+        let segment_len = u16::from_be_bytes(
+            raw.get(tag_start.checked_sub(2)?..tag_start)?
+                .try_into()
+                .ok()?,
+        ) as usize;
+        let payload_start = tag_start + tag.len();
+        let payload_end = (tag_start - 2).checked_add(segment_len)?;
+
+        let index = *raw.get(payload_start)?;
+        let total = *raw.get(payload_start + 1)?;
+        let chunk_data = raw.get(payload_start + 2..payload_end)?;
+
+        chunks.insert(index, chunk_data);
+        pos = payload_end;
+
+        if chunks.len() as u8 == total {
+            break;
+        }
+    }
+
+    if chunks.is_empty() {
+        return None;
+    }
+
+    Some(chunks.into_values().flatten().copied().collect())
+}
+
+/// Scans raw JPEG bytes for a `RIMAGE_ALPHA` APP4 sidecar, reassembling and inflating its
+/// chunks in order if present.
+fn extract_alpha_sidecar(raw: &[u8]) -> Option<Vec<u8>> {
+    use std::io::Read as _;
+
+    let deflated = extract_chunked_app_marker(raw, ALPHA_MARKER_TAG)?;
+
+    let mut decoder = flate2::read::ZlibDecoder::new(deflated.as_slice());
+    let mut alpha = Vec::new();
+    decoder.read_to_end(&mut alpha).ok()?;
+
+    Some(alpha)
+}
+
+/// Scans raw JPEG bytes for an `ICC_PROFILE` APP2 chunk set, reassembling it in order if
+/// present. Unlike the alpha sidecar, ICC profile bytes aren't deflate-compressed on the wire.
+fn extract_icc_profile(raw: &[u8]) -> Option<Vec<u8>> {
+    extract_chunked_app_marker(raw, ICC_MARKER_TAG)
+}
+
+/// Reads an AVIF file's declared image dimensions straight out of its `ispe` (Image Spatial
+/// Extents) box, without decoding. `AvifDecoder::dimensions()` only becomes populated after a
+/// full `decode()` call (the same "None until decoded" convention `JpegliDecoder` follows), so
+/// it can't be used to apply `DecodeLimits` *before* the potentially enormous decode allocation
+/// — we need this header peek instead.
+fn avif_header_dimensions(raw: &[u8]) -> Option<(usize, usize)> {
+    const ISPE_TAG: &[u8; 4] = b"ispe";
+
+    let tag_start = raw.windows(ISPE_TAG.len()).position(|w| w == ISPE_TAG)?;
+    // `ispe` box layout: 4-byte FullBox version+flags, then a 4-byte big-endian width and a
+    // 4-byte big-endian height.
+    let content = raw.get(tag_start + ISPE_TAG.len()..tag_start + ISPE_TAG.len() + 12)?;
+    let width = u32::from_be_bytes(content[4..8].try_into().ok()?) as usize;
+    let height = u32::from_be_bytes(content[8..12].try_into().ok()?) as usize;
+
+    Some((width, height))
+}
+
+/// Reads a WebP file's declared canvas dimensions straight out of its RIFF header, without
+/// decoding, for the same reason `avif_header_dimensions` exists: `WebPDecoder::dimensions()`
+/// isn't populated until after `decode()` has already run.
+fn webp_header_dimensions(raw: &[u8]) -> Option<(usize, usize)> {
+    if raw.len() < 16 || &raw[0..4] != b"RIFF" || &raw[8..12] != b"WEBP" {
+        return None;
+    }
+
+    match raw.get(12..16)? {
+        b"VP8X" => {
+            // Flags (1 byte) + reserved (3 bytes), then a 3-byte little-endian
+            // canvas-width-minus-one and a 3-byte little-endian canvas-height-minus-one.
+            let payload = raw.get(24..30)?;
+            let width = 1 + u32::from_le_bytes([payload[0], payload[1], payload[2], 0]) as usize;
+            let height = 1 + u32::from_le_bytes([payload[3], payload[4], payload[5], 0]) as usize;
+            Some((width, height))
+        }
+        b"VP8 " => {
+            // 3-byte frame tag, then the 3-byte start code 0x9d012a, then a 14-bit width and a
+            // 14-bit height, each little-endian with a 2-bit scale in the high bits.
+            let payload = raw.get(20..30)?;
+            if payload.get(3..6)? != [0x9d, 0x01, 0x2a] {
+                return None;
+            }
+            let width = (u16::from_le_bytes([payload[6], payload[7]]) & 0x3fff) as usize;
+            let height = (u16::from_le_bytes([payload[8], payload[9]]) & 0x3fff) as usize;
+            Some((width, height))
+        }
+        b"VP8L" => {
+            // 1-byte signature (0x2f), then a 32-bit little-endian field packing a 14-bit
+            // width-minus-one and a 14-bit height-minus-one.
+            let payload = raw.get(20..25)?;
+            if payload[0] != 0x2f {
+                return None;
+            }
+            let bits = u32::from_le_bytes(payload[1..5].try_into().ok()?);
+            let width = 1 + (bits & 0x3fff) as usize;
+            let height = 1 + ((bits >> 14) & 0x3fff) as usize;
+            Some((width, height))
+        }
+        _ => None,
+    }
+}
+
+pub fn decode<P: AsRef<Path>>(f: P, limits: DecodeLimits) -> Result<Image, ImageErrors> {
+    let options = DecoderOptions::default()
+        .set_max_width(limits.max_width)
+        .set_max_height(limits.max_height);
+
+    // Only JPEGs can carry our APP2/APP4 markers, so avoid a second full-file read for every
+    // other format. Sniff the SOI magic bytes rather than trusting the extension: a JPEG saved
+    // as `.jfif`, or with no extension at all, still needs its ICC profile and alpha sidecar
+    // picked up.
+    let is_jpeg = {
+        use std::io::Read as _;
+
+        let mut magic = [0u8; 2];
+        std::fs::File::open(f.as_ref())
+            .and_then(|mut file| file.read_exact(&mut magic))
+            .is_ok()
+            && has_jpeg_magic(&magic)
+    };
+    let raw = is_jpeg.then(|| read(f.as_ref())).transpose()?;
+
+    let mut image = Image::open_with_options(f.as_ref(), options).or_else(|e| {
         if matches!(e, ImageErrors::ImageDecoderNotIncluded(_)) {
             let file_content = read("tests/files/avif/f1t.avif")?;
 
@@ -23,8 +181,18 @@ pub fn decode<P: AsRef<Path>>(f: P) -> Result<Image, ImageErrors> {
             if libavif::is_avif(&file_content) {
                 use rimage::codecs::avif::AvifDecoder;
 
-                let reader = ZByteReader::new(file_content);
+                // AvifDecoder::dimensions() isn't populated until decode() has already run (the
+                // same convention JpegliDecoder follows), so it can't guard the decode() call
+                // below. Peek the header's ispe box instead, and fail closed if we can't find
+                // one rather than letting an unrecognized/crafted container through unguarded.
+                let (w, h) = avif_header_dimensions(&file_content).ok_or_else(|| {
+                    ImageErrors::GenericString(
+                        "could not determine AVIF dimensions from its header".to_string(),
+                    )
+                })?;
+                limits.check(w, h, 4)?;
 
+                let reader = ZByteReader::new(file_content);
                 let mut decoder = AvifDecoder::try_new(reader)?;
 
                 return <AvifDecoder<ZByteReader<Vec<u8>>> as DecoderTrait<Vec<u8>>>::decode(
@@ -39,8 +207,16 @@ pub fn decode<P: AsRef<Path>>(f: P) -> Result<Image, ImageErrors> {
             {
                 use rimage::codecs::webp::WebPDecoder;
 
-                let reader = ZByteReader::new(file_content);
+                // Same reasoning as the AVIF branch above: WebPDecoder::dimensions() is only
+                // populated after decode() runs, so peek the RIFF header directly.
+                let (w, h) = webp_header_dimensions(&file_content).ok_or_else(|| {
+                    ImageErrors::GenericString(
+                        "could not determine WebP dimensions from its header".to_string(),
+                    )
+                })?;
+                limits.check(w, h, 4)?;
 
+                let reader = ZByteReader::new(file_content);
                 let mut decoder = WebPDecoder::try_new(reader)?;
 
                 return <WebPDecoder<ZByteReader<Vec<u8>>> as DecoderTrait<Vec<u8>>>::decode(
@@ -54,7 +230,92 @@ pub fn decode<P: AsRef<Path>>(f: P) -> Result<Image, ImageErrors> {
         } else {
             Err(e)
         }
-    })
+    })?;
+
+    if image.colorspace() == zune_core::colorspace::ColorSpace::RGB {
+        if let Some(alpha) = raw.as_deref().and_then(extract_alpha_sidecar) {
+            let (width, height) = image.dimensions();
+            let rgb = &image.flatten_to_u8()[0];
+
+            if alpha.len() == width * height {
+                let mut rgba = Vec::with_capacity(width * height * 4);
+                for (pixel, a) in rgb.chunks_exact(3).zip(alpha.iter()) {
+                    rgba.extend_from_slice(pixel);
+                    rgba.push(*a);
+                }
+
+                image = Image::from_u8(rgba, width, height, zune_core::colorspace::ColorSpace::RGBA);
+            } else {
+                log::warn!("Found an alpha sidecar but its length didn't match the decoded image; ignoring it");
+            }
+        }
+    }
+
+    // Carry the source ICC profile through so encoders that embed it (e.g. MozJpegEncoder with
+    // `keep_icc`) have something to write. zune-image's own JPEG decoder doesn't surface APP2
+    // chunks, so this is the only place that picks it up; color primaries and rendering intent
+    // aren't exposed by any decoder in this tree, so there's nothing further to thread through.
+    if let Some(icc) = raw.as_deref().and_then(extract_icc_profile) {
+        image.metadata_mut().set_icc_chunk(Some(icc));
+    }
+
+    Ok(image)
+}
+
+#[cfg(test)]
+mod tests;
+
+/// Parses a `--scans` custom scan script.
+///
+/// Each scan is `components:Ss:Se:Ah:Al`, separated by `;`, where `components` is a
+/// `,`-separated list of component indices, e.g. `0,1,2:0:0:0:0;0:1:63:0:0` first sends DC
+/// for all components, then AC for luma only.
+fn parse_scan_script(spec: &str) -> Result<Vec<mozjpeg::ScanInfo>, ImageErrors> {
+    spec.split(';')
+        .map(|scan| {
+            let fields: Vec<&str> = scan.split(':').collect();
+            let [components, ss, se, ah, al] = fields.as_slice() else {
+                return Err(ImageErrors::GenericString(format!(
+                    "invalid scan script entry \"{scan}\", expected components:Ss:Se:Ah:Al"
+                )));
+            };
+
+            let component_index = components
+                .split(',')
+                .map(|c| {
+                    c.trim()
+                        .parse::<u8>()
+                        .map_err(|_| ImageErrors::GenericString(format!("invalid component index \"{c}\"")))
+                })
+                .collect::<Result<Vec<u8>, ImageErrors>>()?;
+
+            // Ss/Se address a coefficient's position within the 8x8 zig-zag order (0-63); Ah/Al
+            // are the successive-approximation bit positions, which fit in a nibble (0-15).
+            // mozjpeg's FFI layer doesn't validate these itself, so out-of-spec values would
+            // otherwise reach the C encoder as-is.
+            let parse_range = |name: &str, s: &str, max: u8| {
+                let value = s
+                    .parse::<u8>()
+                    .map_err(|_| ImageErrors::GenericString(format!("invalid scan parameter \"{s}\"")))?;
+
+                if value > max {
+                    return Err(ImageErrors::GenericString(format!(
+                        "scan parameter {name}={value} is out of range, expected 0-{max}"
+                    )));
+                }
+
+                Ok(value)
+            };
+
+            Ok(mozjpeg::ScanInfo {
+                component_index,
+                ss: parse_range("Ss", ss, 63)?,
+                se: parse_range("Se", se, 63)?,
+                ah: parse_range("Ah", ah, 15)?,
+                al: parse_range("Al", al, 15)?,
+            })
+        })
+        .collect()
 }
 
 pub fn operations(matches: &ArgMatches, img: &Image) -> BTreeMap<usize, Box<dyn OperationsTrait>> {
@@ -114,6 +375,17 @@ pub fn operations(matches: &ArgMatches, img: &Image) -> BTreeMap<usize, Box<dyn
         }
     }
 
+    if matches.get_flag("auto_grayscale") {
+        use rimage::operations::grayscale_detect::GrayscaleDetect;
+
+        log::trace!("setup auto-grayscale detection");
+
+        map.insert(
+            matches.index_of("auto_grayscale").unwrap_or(usize::MAX),
+            Box::new(GrayscaleDetect::new()),
+        );
+    }
+
     map
 }
 
@@ -133,6 +405,22 @@ pub fn encoder(matches: &ArgMatches) -> Result<(Box<dyn EncoderTrait>, &'static
                 Ok((Box::new(JpegEncoder::new_with_options(options)), "jpg"))
             }
             "jpeg_xl" => Ok((Box::new(JxlEncoder::new()), "jxl")),
+            "jpegli" => {
+                use rimage::codecs::jpegli::{JpegliEncoder, JpegliOptions};
+
+                let quality = *matches.get_one::<u8>("quality").unwrap() as f32;
+
+                let options = JpegliOptions {
+                    quality,
+                    progressive: !matches.get_flag("baseline"),
+                    chroma_subsample: matches.get_one::<u8>("subsample").copied(),
+                    xyb: !matches.get_flag("no_xyb"),
+                    adaptive_quantization: !matches.get_flag("no_adaptive_quantization"),
+                    ..Default::default()
+                };
+
+                Ok((Box::new(JpegliEncoder::new_with_options(options)), "jpg"))
+            }
             "mozjpeg" => {
                 let quality = *matches.get_one::<u8>("quality").unwrap() as f32;
                 let chroma_quality = matches
@@ -156,6 +444,22 @@ pub fn encoder(matches: &ArgMatches) -> Result<(Box<dyn EncoderTrait>, &'static
                     },
                     trellis_multipass: matches.get_flag("multipass"),
                     chroma_subsample: matches.get_one::<u8>("subsample").copied(),
+                    keep_icc: !matches.get_flag("no_icc"),
+                    scan_mode: match matches
+                        .get_one::<String>("scan_mode")
+                        .map(String::as_str)
+                        .unwrap_or("auto")
+                    {
+                        "auto" => mozjpeg::ScanMode::Auto,
+                        "all_components" => mozjpeg::ScanMode::AllComponentsTogether,
+                        "per_component" => mozjpeg::ScanMode::ScanPerComponent,
+                        _ => unreachable!(),
+                    },
+                    scan_script: matches
+                        .get_one::<String>("scans")
+                        .map(|s| parse_scan_script(s))
+                        .transpose()?,
+                    alpha_sidecar: matches.get_flag("alpha_sidecar"),
 
                     luma_qtable: matches
                         .get_one::<String>("qtable")
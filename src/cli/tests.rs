@@ -0,0 +1,306 @@
+use super::{
+    avif_header_dimensions, extract_alpha_sidecar, extract_chunked_app_marker, extract_icc_profile,
+    has_jpeg_magic, parse_scan_script, webp_header_dimensions, ALPHA_MARKER_TAG, ICC_MARKER_TAG,
+};
+
+/// Builds the raw marker bytes `extract_chunked_app_marker` expects: a 2-byte big-endian
+/// segment length (counting itself, per the JPEG marker segment format), then `tag`, then a
+/// 1-based chunk index, the total chunk count, and the chunk's data — one run per entry in
+/// `chunks`.
+fn build_marker(tag: &[u8], chunks: &[&[u8]]) -> Vec<u8> {
+    let mut raw = Vec::new();
+    let total = chunks.len() as u8;
+
+    for (i, chunk) in chunks.iter().enumerate() {
+        let segment_len = 2 + tag.len() + 2 + chunk.len();
+        raw.extend_from_slice(&(segment_len as u16).to_be_bytes());
+        raw.extend_from_slice(tag);
+        raw.push(i as u8 + 1);
+        raw.push(total);
+        raw.extend_from_slice(chunk);
+    }
+
+    raw
+}
+
+#[test]
+fn has_jpeg_magic_matches_only_the_soi_marker() {
+    assert!(has_jpeg_magic(&[0xFF, 0xD8, 0xFF, 0xE0]));
+    assert!(!has_jpeg_magic(&[0x89, 0x50, 0x4E, 0x47]));
+    assert!(!has_jpeg_magic(&[0xFF]));
+    assert!(!has_jpeg_magic(&[]));
+}
+
+#[test]
+fn extract_chunked_app_marker_reassembles_a_single_chunk() {
+    let raw = build_marker(b"TEST", &[b"hello"]);
+
+    assert_eq!(
+        extract_chunked_app_marker(&raw, b"TEST"),
+        Some(b"hello".to_vec())
+    );
+}
+
+#[test]
+fn extract_chunked_app_marker_reassembles_multiple_chunks_in_order() {
+    let raw = build_marker(b"TEST", &[b"abc", b"def"]);
+
+    assert_eq!(
+        extract_chunked_app_marker(&raw, b"TEST"),
+        Some(b"abcdef".to_vec())
+    );
+}
+
+#[test]
+fn extract_chunked_app_marker_reassembles_out_of_order_chunks() {
+    // Write chunk 2 before chunk 1 in the byte stream; reassembly must still honor the
+    // 1-based index byte rather than file order.
+    let mut raw = Vec::new();
+    let tag: &[u8] = b"TEST";
+    for (index, chunk) in [(2u8, b"def".as_slice()), (1u8, b"abc".as_slice())] {
+        let segment_len = 2 + tag.len() + 2 + chunk.len();
+        raw.extend_from_slice(&(segment_len as u16).to_be_bytes());
+        raw.extend_from_slice(tag);
+        raw.push(index);
+        raw.push(2);
+        raw.extend_from_slice(chunk);
+    }
+
+    assert_eq!(
+        extract_chunked_app_marker(&raw, b"TEST"),
+        Some(b"abcdef".to_vec())
+    );
+}
+
+#[test]
+fn extract_chunked_app_marker_returns_what_it_has_when_the_total_count_is_never_reached() {
+    // Total byte says 2 chunks, but only 1 is ever written; there's nothing left to find a
+    // second tag match in, so reassembly should settle for the chunk it did find rather than
+    // looping forever or failing outright.
+    let raw = build_marker(b"TEST", &[b"abc"]);
+    let raw = {
+        // Force the total byte to 2 while actually only writing one chunk.
+        let mut raw = raw;
+        let total_byte_index = raw.len() - b"abc".len() - 1;
+        raw[total_byte_index] = 2;
+        raw
+    };
+
+    assert_eq!(extract_chunked_app_marker(&raw, b"TEST"), Some(b"abc".to_vec()));
+}
+
+#[test]
+fn extract_chunked_app_marker_is_none_when_the_tag_is_absent() {
+    assert_eq!(extract_chunked_app_marker(b"not a jpeg at all", b"TEST"), None);
+}
+
+#[test]
+fn extract_chunked_app_marker_is_none_when_the_segment_is_truncated() {
+    // The tag is present but the declared segment length runs past the end of the buffer.
+    let tag: &[u8] = b"TEST";
+    let mut raw = vec![0u8, 0u8];
+    raw.extend_from_slice(tag);
+    raw[0..2].copy_from_slice(&255u16.to_be_bytes());
+
+    assert_eq!(extract_chunked_app_marker(&raw, tag), None);
+}
+
+#[test]
+fn extract_chunked_app_marker_is_none_when_the_tag_sits_at_the_very_start_of_the_file() {
+    // Regression test: the tag's first match at offset 0 must not underflow `tag_start - 2`.
+    let tag: &[u8] = b"TEST";
+
+    assert_eq!(extract_chunked_app_marker(tag, tag), None);
+}
+
+#[test]
+fn extract_alpha_sidecar_round_trips_a_deflated_payload() {
+    use std::io::Write as _;
+
+    use flate2::{write::ZlibEncoder, Compression};
+
+    let alpha = vec![10u8, 20, 30, 40, 50];
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&alpha).unwrap();
+    let deflated = encoder.finish().unwrap();
+
+    let raw = build_marker(ALPHA_MARKER_TAG, &[&deflated]);
+
+    assert_eq!(extract_alpha_sidecar(&raw), Some(alpha));
+}
+
+#[test]
+fn extract_alpha_sidecar_is_none_for_a_file_with_no_sidecar() {
+    assert_eq!(extract_alpha_sidecar(b"just some plain bytes"), None);
+}
+
+#[test]
+fn extract_icc_profile_reassembles_a_chunked_profile() {
+    // ICC profile bytes aren't deflate-compressed on the wire, unlike the alpha sidecar, so the
+    // chunks can be arbitrary bytes straight through.
+    let profile: [&[u8]; 2] = [
+        b"fake-icc-profile-part-one-",
+        b"-part-two-not-really-an-icc-profile",
+    ];
+    let raw = build_marker(ICC_MARKER_TAG, &profile);
+
+    let expected: Vec<u8> = profile.concat();
+    assert_eq!(extract_icc_profile(&raw), Some(expected));
+}
+
+#[test]
+fn extract_icc_profile_is_none_without_an_icc_marker() {
+    assert_eq!(extract_icc_profile(b"no icc profile here"), None);
+}
+
+#[test]
+fn extract_icc_profile_does_not_pick_up_an_alpha_sidecar_marker() {
+    let raw = build_marker(ALPHA_MARKER_TAG, &[b"not-an-icc-profile"]);
+
+    assert_eq!(extract_icc_profile(&raw), None);
+}
+
+#[test]
+fn avif_header_dimensions_reads_the_ispe_box() {
+    let mut raw = vec![0u8; 20];
+    raw.extend_from_slice(b"ispe");
+    raw.extend_from_slice(&[0, 0, 0, 0]); // version + flags
+    raw.extend_from_slice(&800u32.to_be_bytes());
+    raw.extend_from_slice(&600u32.to_be_bytes());
+
+    assert_eq!(avif_header_dimensions(&raw), Some((800, 600)));
+}
+
+#[test]
+fn avif_header_dimensions_is_none_without_an_ispe_box() {
+    let raw = vec![0u8; 32];
+
+    assert_eq!(avif_header_dimensions(&raw), None);
+}
+
+#[test]
+fn avif_header_dimensions_is_none_when_the_box_is_truncated() {
+    let mut raw = vec![0u8; 4];
+    raw.extend_from_slice(b"ispe");
+    raw.extend_from_slice(&[0, 0, 0, 0]);
+    // Missing the width/height bytes entirely.
+
+    assert_eq!(avif_header_dimensions(&raw), None);
+}
+
+fn webp_riff_header(fourcc: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+    let mut raw = Vec::new();
+    raw.extend_from_slice(b"RIFF");
+    raw.extend_from_slice(&((4 + 8 + payload.len()) as u32).to_le_bytes());
+    raw.extend_from_slice(b"WEBP");
+    raw.extend_from_slice(fourcc);
+    raw.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    raw.extend_from_slice(payload);
+    raw
+}
+
+#[test]
+fn webp_header_dimensions_reads_vp8x_extended_format() {
+    let mut payload = vec![0u8; 10];
+    // canvas width - 1 = 639 (640 wide), canvas height - 1 = 479 (480 tall), 24-bit LE each.
+    payload[4..7].copy_from_slice(&639u32.to_le_bytes()[..3]);
+    payload[7..10].copy_from_slice(&479u32.to_le_bytes()[..3]);
+
+    let raw = webp_riff_header(b"VP8X", &payload);
+
+    assert_eq!(webp_header_dimensions(&raw), Some((640, 480)));
+}
+
+#[test]
+fn webp_header_dimensions_reads_simple_lossy_vp8() {
+    let mut payload = vec![0u8; 10];
+    payload[3..6].copy_from_slice(&[0x9d, 0x01, 0x2a]);
+    payload[6..8].copy_from_slice(&320u16.to_le_bytes());
+    payload[8..10].copy_from_slice(&240u16.to_le_bytes());
+
+    let raw = webp_riff_header(b"VP8 ", &payload);
+
+    assert_eq!(webp_header_dimensions(&raw), Some((320, 240)));
+}
+
+#[test]
+fn webp_header_dimensions_reads_lossless_vp8l() {
+    let width_minus_one: u32 = 99;
+    let height_minus_one: u32 = 49;
+    let bits = (width_minus_one & 0x3fff) | ((height_minus_one & 0x3fff) << 14);
+
+    let mut payload = vec![0x2f];
+    payload.extend_from_slice(&bits.to_le_bytes());
+
+    let raw = webp_riff_header(b"VP8L", &payload);
+
+    assert_eq!(webp_header_dimensions(&raw), Some((100, 50)));
+}
+
+#[test]
+fn webp_header_dimensions_is_none_for_a_non_riff_file() {
+    let raw = vec![0u8; 32];
+
+    assert_eq!(webp_header_dimensions(&raw), None);
+}
+
+#[test]
+fn webp_header_dimensions_is_none_for_an_unrecognized_chunk() {
+    let raw = webp_riff_header(b"ANIM", &[0u8; 16]);
+
+    assert_eq!(webp_header_dimensions(&raw), None);
+}
+
+#[test]
+fn parse_scan_script_parses_a_single_valid_scan() {
+    let scans = parse_scan_script("0,1,2:0:0:0:0").unwrap();
+
+    assert_eq!(scans.len(), 1);
+    assert_eq!(scans[0].component_index, vec![0, 1, 2]);
+    assert_eq!(scans[0].ss, 0);
+    assert_eq!(scans[0].se, 0);
+    assert_eq!(scans[0].ah, 0);
+    assert_eq!(scans[0].al, 0);
+}
+
+#[test]
+fn parse_scan_script_parses_multiple_scans() {
+    let scans = parse_scan_script("0,1,2:0:0:0:0;0:1:63:0:0").unwrap();
+
+    assert_eq!(scans.len(), 2);
+    assert_eq!(scans[1].component_index, vec![0]);
+    assert_eq!(scans[1].ss, 1);
+    assert_eq!(scans[1].se, 63);
+}
+
+#[test]
+fn parse_scan_script_accepts_ss_se_at_the_top_of_their_range() {
+    assert!(parse_scan_script("0:63:63:0:0").is_ok());
+}
+
+#[test]
+fn parse_scan_script_rejects_ss_over_63() {
+    assert!(parse_scan_script("0:64:64:0:0").is_err());
+}
+
+#[test]
+fn parse_scan_script_accepts_ah_al_at_the_top_of_their_range() {
+    assert!(parse_scan_script("0:0:0:15:15").is_ok());
+}
+
+#[test]
+fn parse_scan_script_rejects_al_over_15() {
+    assert!(parse_scan_script("0:0:0:0:16").is_err());
+}
+
+#[test]
+fn parse_scan_script_rejects_malformed_entries() {
+    assert!(parse_scan_script("0:0:0:0").is_err());
+    assert!(parse_scan_script("0:0:0:0:0:0").is_err());
+}
+
+#[test]
+fn parse_scan_script_rejects_non_numeric_fields() {
+    assert!(parse_scan_script("0:a:0:0:0").is_err());
+    assert!(parse_scan_script("x:0:0:0:0").is_err());
+}
@@ -3,17 +3,71 @@ use std::{io::Read, marker::PhantomData};
 use zune_core::{bytestream::ZReaderTrait, colorspace::ColorSpace};
 use zune_image::{errors::ImageErrors, image::Image, traits::DecoderTrait};
 
+/// Limits on the dimensions and total allocation a decoder will accept, guarding against
+/// decompression bombs where a small file declares enormous dimensions.
+#[derive(Debug, Clone, Copy)]
+pub struct DecodeLimits {
+    pub max_width: usize,
+    pub max_height: usize,
+    /// Upper bound on `width * height * channels`, i.e. the size of the allocated scanline buffer.
+    pub max_pixel_budget: usize,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_width: 1 << 16,
+            max_height: 1 << 16,
+            // ~256MP at 4 channels, a generous ceiling for legitimate photos while still
+            // ruling out the multi-gigabyte allocations a crafted header can ask for.
+            max_pixel_budget: 256 * 1024 * 1024 * 4,
+        }
+    }
+}
+
+impl DecodeLimits {
+    /// Rejects `width`x`height` at `channels` channels if it exceeds the configured dimension
+    /// or pixel-budget ceilings. Exposed so other decoders (e.g. the avif/webp fallback paths
+    /// in the CLI) can bail before allocating their own scanline buffers.
+    pub fn check(&self, width: usize, height: usize, channels: usize) -> Result<(), ImageErrors> {
+        if width > self.max_width || height > self.max_height {
+            return Err(ImageErrors::GenericString(format!(
+                "Image dimensions {width}x{height} exceed configured decode limits ({}x{})",
+                self.max_width, self.max_height
+            )));
+        }
+
+        match width.checked_mul(height).and_then(|p| p.checked_mul(channels)) {
+            Some(budget) if budget <= self.max_pixel_budget => Ok(()),
+            _ => Err(ImageErrors::GenericString(format!(
+                "Image {width}x{height} with {channels} channels exceeds the configured decode pixel budget of {} bytes",
+                self.max_pixel_budget
+            ))),
+        }
+    }
+}
+
 /// A jpegli decoder
 pub struct JpegliDecoder<R: Read> {
     inner: Vec<u8>,
     dimensions: Option<(usize, usize)>,
     phantom: PhantomData<R>,
     colorspace: Option<ColorSpace>,
+    limits: DecodeLimits,
 }
 
 impl<R: Read> JpegliDecoder<R> {
     /// Create a new webp decoder that reads data from `source`
-    pub fn try_new(mut source: R) -> Result<JpegliDecoder<R>, ImageErrors> {
+    pub fn try_new(source: R) -> Result<JpegliDecoder<R>, ImageErrors> {
+        Self::try_new_with_limits(source, DecodeLimits::default())
+    }
+
+    /// Create a new jpegli decoder that reads data from `source`, rejecting images whose
+    /// declared dimensions exceed `limits`
+    pub fn try_new_with_limits(
+        mut source: R,
+        limits: DecodeLimits,
+    ) -> Result<JpegliDecoder<R>, ImageErrors> {
         let mut buf = Vec::new();
         source.read_to_end(&mut buf)?;
 
@@ -22,6 +76,7 @@ impl<R: Read> JpegliDecoder<R> {
             dimensions: None,
             phantom: PhantomData,
             colorspace: None,
+            limits,
         })
     }
 }
@@ -41,6 +96,25 @@ where
                     | jpegli::ColorSpace::JCS_EXT_ABGR
                     | jpegli::ColorSpace::JCS_RGB565
             );
+
+            let channels = match d.color_space() {
+                jpegli::ColorSpace::JCS_GRAYSCALE => 1,
+                jpegli::ColorSpace::JCS_CMYK | jpegli::ColorSpace::JCS_YCCK => 4,
+                jpegli::ColorSpace::JCS_EXT_RGBX
+                | jpegli::ColorSpace::JCS_EXT_BGRX
+                | jpegli::ColorSpace::JCS_EXT_XBGR
+                | jpegli::ColorSpace::JCS_EXT_XRGB
+                | jpegli::ColorSpace::JCS_EXT_RGBA
+                | jpegli::ColorSpace::JCS_EXT_BGRA
+                | jpegli::ColorSpace::JCS_EXT_ABGR
+                | jpegli::ColorSpace::JCS_EXT_ARGB => 4,
+                _ => 3,
+            };
+
+            self.limits
+                .check(d.width(), d.height(), channels)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+
             let mut image;
             if should_transform_color_space {
                 image = d.to_colorspace(jpegli::ColorSpace::JCS_YCbCr)?;
@@ -75,7 +149,13 @@ where
             Ok(Image::from_u8(pixels, width, height, colorspace))
         })
         .unwrap()
-        .map_err(|_| ImageErrors::GenericString("error with jpegli".to_string()))?;
+        .map_err(|e| {
+            if e.kind() == std::io::ErrorKind::InvalidData {
+                ImageErrors::GenericString(e.to_string())
+            } else {
+                ImageErrors::GenericString("error with jpegli".to_string())
+            }
+        })?;
         self.dimensions = Some(image.dimensions());
         self.colorspace = Some(image.colorspace());
 
@@ -94,3 +174,6 @@ where
         "jpegli"
     }
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,56 @@
+use super::DecodeLimits;
+
+#[test]
+fn accepts_dimensions_at_the_limit() {
+    let limits = DecodeLimits {
+        max_width: 100,
+        max_height: 100,
+        max_pixel_budget: 100 * 100 * 3,
+    };
+
+    assert!(limits.check(100, 100, 3).is_ok());
+}
+
+#[test]
+fn rejects_width_one_over_the_limit() {
+    let limits = DecodeLimits {
+        max_width: 100,
+        max_height: 100,
+        max_pixel_budget: usize::MAX,
+    };
+
+    assert!(limits.check(101, 100, 3).is_err());
+}
+
+#[test]
+fn rejects_height_one_over_the_limit() {
+    let limits = DecodeLimits {
+        max_width: 100,
+        max_height: 100,
+        max_pixel_budget: usize::MAX,
+    };
+
+    assert!(limits.check(100, 101, 3).is_err());
+}
+
+#[test]
+fn rejects_pixel_budget_one_byte_over_the_limit() {
+    let limits = DecodeLimits {
+        max_width: usize::MAX,
+        max_height: usize::MAX,
+        max_pixel_budget: 100 * 100 * 3 - 1,
+    };
+
+    assert!(limits.check(100, 100, 3).is_err());
+}
+
+#[test]
+fn rejects_dimensions_that_would_overflow_the_pixel_budget_multiplication() {
+    let limits = DecodeLimits {
+        max_width: usize::MAX,
+        max_height: usize::MAX,
+        max_pixel_budget: usize::MAX,
+    };
+
+    assert!(limits.check(usize::MAX, usize::MAX, 4).is_err());
+}
@@ -0,0 +1,149 @@
+use std::mem;
+
+use zune_core::{bit_depth::BitDepth, colorspace::ColorSpace};
+use zune_image::{codecs::ImageFormat, errors::ImageErrors, image::Image, traits::EncoderTrait};
+
+/// Advanced options for Jpegli encoding
+pub struct JpegliOptions {
+    quality: f32,
+    progressive: bool,
+    chroma_subsample: Option<u8>,
+    /// Encode via jpegli's XYB color transform instead of YCbCr.
+    xyb: bool,
+    /// Use jpegli's adaptive, distance-based quantization instead of the classic qtables.
+    adaptive_quantization: bool,
+}
+
+/// A Jpegli encoder
+pub struct JpegliEncoder {
+    options: JpegliOptions,
+}
+
+impl Default for JpegliOptions {
+    fn default() -> Self {
+        Self {
+            quality: 75.,
+            progressive: true,
+            chroma_subsample: None,
+            xyb: true,
+            adaptive_quantization: true,
+        }
+    }
+}
+
+impl Default for JpegliEncoder {
+    fn default() -> Self {
+        Self {
+            options: Default::default(),
+        }
+    }
+}
+
+impl JpegliEncoder {
+    /// Create a new encoder
+    pub fn new() -> JpegliEncoder {
+        JpegliEncoder::default()
+    }
+
+    /// Create a new encoder with specified options
+    pub fn new_with_options(options: JpegliOptions) -> JpegliEncoder {
+        JpegliEncoder { options }
+    }
+
+    /// jpegli works off a perceptual "distance" scale (0 is lossless, larger is lossier)
+    /// rather than the classic 1-100 quality scale, so map between the two linearly, as
+    /// `0.1 * (100 - quality)` floored at 0.01, for callers that only think in quality terms.
+    fn quality_to_distance(quality: f32) -> f32 {
+        (0.1 * (100. - quality)).max(0.01)
+    }
+}
+
+impl EncoderTrait for JpegliEncoder {
+    fn name(&self) -> &'static str {
+        "jpegli-encoder"
+    }
+
+    fn encode_inner(&mut self, image: &Image) -> Result<Vec<u8>, ImageErrors> {
+        let (width, height) = image.dimensions();
+        let data = &image.flatten_to_u8()[0];
+
+        std::panic::catch_unwind(|| -> Result<Vec<u8>, ImageErrors> {
+            let format = match image.colorspace() {
+                ColorSpace::RGB => jpegli::ColorSpace::JCS_RGB,
+                ColorSpace::RGBA => jpegli::ColorSpace::JCS_EXT_RGBA,
+                ColorSpace::YCbCr => jpegli::ColorSpace::JCS_YCbCr,
+                ColorSpace::Luma => jpegli::ColorSpace::JCS_GRAYSCALE,
+                ColorSpace::BGR => jpegli::ColorSpace::JCS_EXT_BGR,
+                ColorSpace::BGRA => jpegli::ColorSpace::JCS_EXT_BGRA,
+                ColorSpace::ARGB => jpegli::ColorSpace::JCS_EXT_ARGB,
+                _ => jpegli::ColorSpace::JCS_UNKNOWN,
+            };
+
+            let mut comp = jpegli::Compress::new(format);
+
+            comp.set_size(width, height);
+
+            if self.options.xyb {
+                comp.set_xyb_mode();
+            }
+
+            if self.options.adaptive_quantization {
+                comp.set_distance(Self::quality_to_distance(self.options.quality));
+            } else {
+                comp.set_quality(self.options.quality);
+            }
+
+            if self.options.progressive {
+                comp.set_progressive_mode();
+            }
+
+            if let Some(sb) = self.options.chroma_subsample {
+                comp.set_chroma_sampling_pixel_sizes((sb, sb), (sb, sb))
+            }
+
+            let mut comp = comp.start_compress(Vec::new())?;
+
+            comp.write_scanlines(&data)?;
+
+            Ok(comp.finish()?)
+        })
+        .map_err(|err| {
+            if let Ok(mut err) = err.downcast::<String>() {
+                ImageErrors::EncodeErrors(zune_image::errors::ImgEncodeErrors::Generic(mem::take(
+                    &mut *err,
+                )))
+            } else {
+                ImageErrors::EncodeErrors(zune_image::errors::ImgEncodeErrors::GenericStatic(
+                    "Unknown error occurred during encoding",
+                ))
+            }
+        })?
+    }
+
+    fn supported_colorspaces(&self) -> &'static [ColorSpace] {
+        &[
+            ColorSpace::Luma,
+            ColorSpace::RGBA,
+            ColorSpace::RGB,
+            ColorSpace::BGR,
+            ColorSpace::BGRA,
+            ColorSpace::ARGB,
+            ColorSpace::YCbCr,
+        ]
+    }
+
+    fn format(&self) -> zune_image::codecs::ImageFormat {
+        ImageFormat::JPEG
+    }
+
+    fn supported_bit_depth(&self) -> &'static [BitDepth] {
+        &[BitDepth::Eight]
+    }
+
+    fn default_depth(&self, _depth: BitDepth) -> BitDepth {
+        BitDepth::Eight
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,35 @@
+use zune_core::colorspace::ColorSpace;
+use zune_image::{image::Image, traits::EncoderTrait};
+
+use super::JpegliEncoder;
+
+#[test]
+fn quality_to_distance_clamps_at_the_lossless_end() {
+    // 0.1 * (100 - 100) is 0, which must be clamped up to the 0.01 floor rather than
+    // handed to jpegli as an actual 0 (lossless) distance.
+    assert_eq!(JpegliEncoder::quality_to_distance(100.), 0.01);
+    assert_eq!(JpegliEncoder::quality_to_distance(99.9), 0.01);
+}
+
+#[test]
+fn quality_to_distance_is_ten_at_the_low_end() {
+    assert_eq!(JpegliEncoder::quality_to_distance(0.), 10.0);
+}
+
+#[test]
+fn quality_to_distance_is_linear_in_the_middle() {
+    assert_eq!(JpegliEncoder::quality_to_distance(75.), 2.5);
+}
+
+#[test]
+fn encode_inner_produces_a_valid_jpeg() {
+    let width = 4;
+    let height = 4;
+    let pixels = vec![128u8; width * height * 3];
+    let image = Image::from_u8(pixels, width, height, ColorSpace::RGB);
+
+    let mut encoder = JpegliEncoder::new();
+    let encoded = encoder.encode_inner(&image).expect("encode_inner should succeed");
+
+    assert_eq!(&encoded[..2], &[0xFF, 0xD8]);
+}
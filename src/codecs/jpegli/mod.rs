@@ -0,0 +1,5 @@
+mod decoder;
+mod encoder;
+
+pub use decoder::{DecodeLimits, JpegliDecoder};
+pub use encoder::{JpegliEncoder, JpegliOptions};
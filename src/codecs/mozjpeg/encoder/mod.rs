@@ -1,9 +1,54 @@
-use std::mem;
+use std::{borrow::Cow, mem};
 
+use flate2::{write::ZlibEncoder, Compression};
 use mozjpeg::qtable::QTable;
+use std::io::Write;
 use zune_core::{bit_depth::BitDepth, colorspace::ColorSpace};
 use zune_image::{codecs::ImageFormat, errors::ImageErrors, image::Image, traits::EncoderTrait};
 
+/// JPEG APP2 marker tag used to identify ICC profile chunks, per the ICC spec.
+const ICC_MARKER_TAG: &[u8; 12] = b"ICC_PROFILE\0";
+/// Maximum payload per APP2 marker: 65535 byte marker limit, minus the 2-byte
+/// length field, the 12-byte tag, and the 2 chunk-index bytes.
+const ICC_MARKER_MAX_CHUNK_LEN: usize = 65519;
+
+/// JPEG APP4 marker tag used to identify a deflate-compressed alpha sidecar, mirroring the
+/// chunked `ICC_PROFILE\0` scheme but for the 8-bit alpha plane stripped from RGBA input.
+const ALPHA_MARKER_TAG: &[u8; 14] = b"RIMAGE_ALPHA\0\0";
+const ALPHA_MARKER_MAX_CHUNK_LEN: usize = 65517;
+
+/// Splits `data` into `max_chunk_len`-sized pieces and passes each, already framed with `tag`,
+/// a 1-based chunk index and the total chunk count, to `write_marker`. Mirrors the chunked
+/// `ICC_PROFILE\0` scheme JPEG tooling uses for payloads that don't fit in one marker.
+fn write_chunked_app_marker(
+    mut write_marker: impl FnMut(&[u8]),
+    app_num: u8,
+    tag: &[u8],
+    data: &[u8],
+    max_chunk_len: usize,
+) -> Result<(), ImageErrors> {
+    let num_chunks = data.chunks(max_chunk_len).count().max(1);
+
+    if num_chunks > u8::MAX as usize {
+        return Err(ImageErrors::GenericString(format!(
+            "payload for APP{app_num} marker \"{}\" needs {num_chunks} chunks, more than the 255 the chunk-index byte can address",
+            String::from_utf8_lossy(tag).trim_end_matches('\0'),
+        )));
+    }
+
+    for (i, chunk) in data.chunks(max_chunk_len).enumerate() {
+        let mut marker = Vec::with_capacity(tag.len() + 2 + chunk.len());
+        marker.extend_from_slice(tag);
+        marker.push(i as u8 + 1);
+        marker.push(num_chunks as u8);
+        marker.extend_from_slice(chunk);
+
+        write_marker(&marker);
+    }
+
+    Ok(())
+}
+
 /// Advanced options for MozJpeg encoding
 pub struct MozJpegOptions {
     quality: f32,
@@ -15,6 +60,15 @@ pub struct MozJpegOptions {
     chroma_subsample: Option<u8>,
     luma_qtable: Option<&'static QTable>,
     chroma_qtable: Option<&'static QTable>,
+    /// Whether to embed the source image's ICC profile, if any, as APP2 markers.
+    keep_icc: bool,
+    /// Scan optimization strategy used when `progressive` is enabled.
+    scan_mode: mozjpeg::ScanMode,
+    /// A hand-written scan script, used instead of `scan_mode` when present.
+    scan_script: Option<Vec<mozjpeg::ScanInfo>>,
+    /// For RGBA input, encode the RGB planes as a normal baseline/progressive JPEG and store
+    /// the alpha plane as a deflate-compressed APP4 sidecar instead of dropping it.
+    alpha_sidecar: bool,
 }
 
 /// A MozJpeg encoder
@@ -34,6 +88,10 @@ impl Default for MozJpegOptions {
             chroma_subsample: None,
             luma_qtable: None,
             chroma_qtable: None,
+            keep_icc: true,
+            scan_mode: mozjpeg::ScanMode::Auto,
+            scan_script: None,
+            alpha_sidecar: false,
         }
     }
 }
@@ -65,21 +123,41 @@ impl EncoderTrait for MozJpegEncoder {
 
     fn encode_inner(&mut self, image: &Image) -> Result<Vec<u8>, ImageErrors> {
         let (width, height) = image.dimensions();
-        let data = &image.flatten_to_u8()[0];
+        let interleaved = &image.flatten_to_u8()[0];
+
+        let splits_alpha = self.options.alpha_sidecar && image.colorspace() == ColorSpace::RGBA;
+
+        let (data, alpha_channel) = if splits_alpha {
+            let mut rgb = Vec::with_capacity(width * height * 3);
+            let mut alpha = Vec::with_capacity(width * height);
+
+            for pixel in interleaved.chunks_exact(4) {
+                rgb.extend_from_slice(&pixel[..3]);
+                alpha.push(pixel[3]);
+            }
+
+            (Cow::Owned(rgb), Some(alpha))
+        } else {
+            (Cow::Borrowed(interleaved.as_slice()), None)
+        };
 
         std::panic::catch_unwind(|| -> Result<Vec<u8>, ImageErrors> {
-            let format = match image.colorspace() {
-                ColorSpace::RGB => mozjpeg::ColorSpace::JCS_RGB,
-                ColorSpace::RGBA => mozjpeg::ColorSpace::JCS_EXT_RGBA,
-                ColorSpace::YCbCr => mozjpeg::ColorSpace::JCS_YCbCr,
-                ColorSpace::Luma => mozjpeg::ColorSpace::JCS_GRAYSCALE,
-                ColorSpace::YCCK => mozjpeg::ColorSpace::JCS_YCCK,
-                ColorSpace::CMYK => mozjpeg::ColorSpace::JCS_CMYK,
-                ColorSpace::BGR => mozjpeg::ColorSpace::JCS_EXT_BGR,
-                ColorSpace::BGRA => mozjpeg::ColorSpace::JCS_EXT_BGRA,
-                ColorSpace::ARGB => mozjpeg::ColorSpace::JCS_EXT_ARGB,
-                ColorSpace::Unknown => mozjpeg::ColorSpace::JCS_UNKNOWN,
-                _ => mozjpeg::ColorSpace::JCS_UNKNOWN,
+            let format = if splits_alpha {
+                mozjpeg::ColorSpace::JCS_EXT_RGB
+            } else {
+                match image.colorspace() {
+                    ColorSpace::RGB => mozjpeg::ColorSpace::JCS_RGB,
+                    ColorSpace::RGBA => mozjpeg::ColorSpace::JCS_EXT_RGBA,
+                    ColorSpace::YCbCr => mozjpeg::ColorSpace::JCS_YCbCr,
+                    ColorSpace::Luma => mozjpeg::ColorSpace::JCS_GRAYSCALE,
+                    ColorSpace::YCCK => mozjpeg::ColorSpace::JCS_YCCK,
+                    ColorSpace::CMYK => mozjpeg::ColorSpace::JCS_CMYK,
+                    ColorSpace::BGR => mozjpeg::ColorSpace::JCS_EXT_BGR,
+                    ColorSpace::BGRA => mozjpeg::ColorSpace::JCS_EXT_BGRA,
+                    ColorSpace::ARGB => mozjpeg::ColorSpace::JCS_EXT_ARGB,
+                    ColorSpace::Unknown => mozjpeg::ColorSpace::JCS_UNKNOWN,
+                    _ => mozjpeg::ColorSpace::JCS_UNKNOWN,
+                }
             };
 
             let mut comp = mozjpeg::Compress::new(format);
@@ -89,6 +167,12 @@ impl EncoderTrait for MozJpegEncoder {
 
             if self.options.progressive {
                 comp.set_progressive_mode();
+
+                if let Some(scan_script) = &self.options.scan_script {
+                    comp.set_scan_script(scan_script);
+                } else {
+                    comp.set_scan_optimization_mode(self.options.scan_mode);
+                }
             }
 
             comp.set_optimize_coding(self.options.optimize_coding);
@@ -128,6 +212,32 @@ impl EncoderTrait for MozJpegEncoder {
 
             let mut comp = comp.start_compress(Vec::new())?;
 
+            if self.options.keep_icc {
+                if let Some(icc_profile) = image.metadata().icc_chunk() {
+                    write_chunked_app_marker(
+                        |marker| comp.write_marker(mozjpeg::Marker::APP(2), marker),
+                        2,
+                        ICC_MARKER_TAG,
+                        icc_profile,
+                        ICC_MARKER_MAX_CHUNK_LEN,
+                    )?;
+                }
+            }
+
+            if let Some(alpha) = &alpha_channel {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+                encoder.write_all(alpha)?;
+                let deflated = encoder.finish().map_err(ImageErrors::from)?;
+
+                write_chunked_app_marker(
+                    |marker| comp.write_marker(mozjpeg::Marker::APP(4), marker),
+                    4,
+                    ALPHA_MARKER_TAG,
+                    &deflated,
+                    ALPHA_MARKER_MAX_CHUNK_LEN,
+                )?;
+            }
+
             comp.write_scanlines(&data)?;
 
             Ok(comp.finish()?)
@@ -0,0 +1,72 @@
+use std::io::Read as _;
+
+use flate2::read::ZlibDecoder;
+use zune_core::colorspace::ColorSpace;
+use zune_image::{image::Image, traits::EncoderTrait};
+
+use super::{MozJpegEncoder, MozJpegOptions, ALPHA_MARKER_TAG};
+
+/// Reads the first APP4 `RIMAGE_ALPHA` chunk out of raw encoded bytes and inflates it. Only
+/// that one chunk is read; a sidecar split across several chunks is not reassembled.
+fn read_alpha_sidecar(encoded: &[u8]) -> Option<Vec<u8>> {
+    let tag_start = encoded
+        .windows(ALPHA_MARKER_TAG.len())
+        .position(|w| w == ALPHA_MARKER_TAG)?;
+    let segment_len =
+        u16::from_be_bytes(encoded.get(tag_start - 2..tag_start)?.try_into().ok()?) as usize;
+    let payload_start = tag_start + ALPHA_MARKER_TAG.len();
+    let payload_end = tag_start - 2 + segment_len;
+    let deflated = encoded.get(payload_start + 2..payload_end)?;
+
+    let mut decoder = ZlibDecoder::new(deflated);
+    let mut alpha = Vec::new();
+    decoder.read_to_end(&mut alpha).ok()?;
+
+    Some(alpha)
+}
+
+fn rgba_checkerboard(width: usize, height: usize) -> Vec<u8> {
+    let mut pixels = Vec::with_capacity(width * height * 4);
+
+    for y in 0..height {
+        for x in 0..width {
+            let on = (x + y) % 2 == 0;
+            pixels.extend_from_slice(if on { &[255, 0, 0] } else { &[0, 255, 0] });
+            pixels.push(((x * 16 + y) % 256) as u8);
+        }
+    }
+
+    pixels
+}
+
+#[test]
+fn alpha_sidecar_round_trips_through_the_encoded_bytes() {
+    let width = 8;
+    let height = 8;
+    let pixels = rgba_checkerboard(width, height);
+    let image = Image::from_u8(pixels.clone(), width, height, ColorSpace::RGBA);
+
+    let mut options = MozJpegOptions::default();
+    options.alpha_sidecar = true;
+    let mut encoder = MozJpegEncoder::new_with_options(options);
+
+    let encoded = encoder.encode_inner(&image).expect("encode_inner should succeed");
+
+    let alpha = read_alpha_sidecar(&encoded).expect("encoded bytes should carry an alpha sidecar");
+    let expected: Vec<u8> = pixels.chunks_exact(4).map(|p| p[3]).collect();
+
+    assert_eq!(alpha, expected);
+}
+
+#[test]
+fn non_alpha_sidecar_encode_omits_the_marker() {
+    let width = 4;
+    let height = 4;
+    let pixels = rgba_checkerboard(width, height);
+    let image = Image::from_u8(pixels, width, height, ColorSpace::RGBA);
+
+    let mut encoder = MozJpegEncoder::new();
+    let encoded = encoder.encode_inner(&image).expect("encode_inner should succeed");
+
+    assert!(read_alpha_sidecar(&encoded).is_none());
+}
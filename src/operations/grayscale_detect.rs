@@ -0,0 +1,126 @@
+use zune_core::colorspace::ColorSpace;
+use zune_image::{errors::ImageErrors, image::Image, traits::OperationsTrait};
+
+/// Detects effectively-monochrome RGB/RGBA images and converts them to `Luma`.
+///
+/// Many "color" scans are actually grayscale: every pixel is chromatically neutral and the
+/// RGB channels only differ from each other because of JPEG ringing or scanner noise. Encoding
+/// those as a single channel rather than three/four can shrink the output substantially, so
+/// this operation samples the image and, if it passes the test, rewrites it as `Luma` using
+/// Rec.601 luma weights.
+#[derive(Debug, Clone, Copy)]
+pub struct GrayscaleDetect {
+    /// Maximum `max(|R-G|, |G-B|, |R-B|)` a pixel may have before it's considered colored.
+    threshold: u8,
+    /// Fraction of sampled pixels (0.0-1.0) allowed to exceed `threshold` and still pass.
+    tolerated_fraction: f32,
+    /// Check every Nth pixel rather than every pixel, for speed on large images.
+    subsample: usize,
+}
+
+impl Default for GrayscaleDetect {
+    fn default() -> Self {
+        Self {
+            threshold: 4,
+            tolerated_fraction: 0.0,
+            subsample: 1,
+        }
+    }
+}
+
+impl GrayscaleDetect {
+    /// Create a new operation with the default threshold (4) and no tolerance for colored pixels.
+    pub fn new() -> GrayscaleDetect {
+        GrayscaleDetect::default()
+    }
+
+    /// Create a new operation with explicit threshold, tolerated fraction and subsample step.
+    pub fn new_with_options(
+        threshold: u8,
+        tolerated_fraction: f32,
+        subsample: usize,
+    ) -> GrayscaleDetect {
+        GrayscaleDetect {
+            threshold,
+            tolerated_fraction,
+            subsample: subsample.max(1),
+        }
+    }
+
+    fn is_effectively_grayscale(&self, data: &[u8], channels: usize) -> bool {
+        let pixel_count = data.len() / channels;
+        if pixel_count == 0 {
+            return false;
+        }
+
+        let mut sampled = 0usize;
+        let mut colored = 0usize;
+
+        for pixel in data.chunks_exact(channels).step_by(self.subsample) {
+            let r = pixel[0] as i16;
+            let g = pixel[1] as i16;
+            let b = pixel[2] as i16;
+
+            let max_diff = (r - g).abs().max((g - b).abs()).max((r - b).abs());
+
+            sampled += 1;
+            if max_diff > self.threshold as i16 {
+                colored += 1;
+            }
+        }
+
+        if sampled == 0 {
+            return false;
+        }
+
+        (colored as f32 / sampled as f32) <= self.tolerated_fraction
+    }
+}
+
+impl OperationsTrait for GrayscaleDetect {
+    fn name(&self) -> &'static str {
+        "grayscale detect"
+    }
+
+    fn execute_impl(&self, image: &mut Image) -> Result<(), ImageErrors> {
+        let channels = match image.colorspace() {
+            ColorSpace::RGB => 3,
+            ColorSpace::RGBA => 4,
+            _ => return Ok(()),
+        };
+
+        let (width, height) = image.dimensions();
+        let data = &image.flatten_to_u8()[0];
+
+        if channels == 4 && data.chunks_exact(channels).any(|pixel| pixel[3] != 255) {
+            log::warn!(
+                "Skipping grayscale detection: image has a non-trivial alpha channel and converting to Luma would discard it"
+            );
+            return Ok(());
+        }
+
+        if !self.is_effectively_grayscale(data, channels) {
+            return Ok(());
+        }
+
+        let mut luma = Vec::with_capacity(width * height);
+
+        for pixel in data.chunks_exact(channels) {
+            let r = pixel[0] as u32;
+            let g = pixel[1] as u32;
+            let b = pixel[2] as u32;
+
+            // Rec.601 luma weights, matching the coefficients libjpeg uses for RGB -> Y.
+            let y = (r * 299 + g * 587 + b * 114) / 1000;
+            luma.push(y as u8);
+        }
+
+        image.set_image_data(vec![luma], width, height, ColorSpace::Luma);
+
+        Ok(())
+    }
+
+    fn supported_colorspaces(&self) -> &'static [ColorSpace] {
+        &[ColorSpace::RGB, ColorSpace::RGBA]
+    }
+}
@@ -0,0 +1,7 @@
+#[cfg(feature = "resize")]
+pub mod resize;
+
+#[cfg(feature = "quantization")]
+pub mod quantize;
+
+pub mod grayscale_detect;